@@ -1,15 +1,22 @@
 use axum::{
     body::Body,
     extract::State,
-    http::{header, HeaderValue, Method, Request, StatusCode, Uri},
+    http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode, Uri},
     response::{IntoResponse, Response},
     routing::get,
     Router,
 };
 use bytes::Bytes;
 use moka::future::Cache;
+use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, IntCounterVec, Opts, Registry, TextEncoder};
 use reqwest::Client;
-use std::time::Duration;
+use std::collections::HashSet;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::timeout;
 use tower_http::compression::CompressionLayer;
 use tracing::{error, info, warn};
 
@@ -20,9 +27,26 @@ struct Config {
     host: String,
     port: u16,
     cache_ttl_seconds: u64,
+    cache_stale_seconds: u64,
     cache_max_capacity: u64,
-    allowed_origin_suffix: String,
-    allowed_origin_exact: String,
+    allowed_origin_exacts: Vec<String>,
+    allowed_origin_suffixes: Vec<String>,
+    cors_allowed_methods: String,
+    cors_allowed_headers: String,
+    max_uri_path_len: usize,
+    max_query_len: usize,
+    request_timeout_seconds: u64,
+    access_log_path: Option<String>,
+    access_log_max_bytes: u64,
+}
+
+fn parse_comma_list(var: &str, default: &str) -> Vec<String> {
+    std::env::var(var)
+        .unwrap_or_else(|_| default.to_string())
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
 }
 
 impl Config {
@@ -47,14 +71,37 @@ impl Config {
                 .ok()
                 .and_then(|p| p.parse().ok())
                 .unwrap_or(600),
+            cache_stale_seconds: std::env::var("CACHE_STALE_SECONDS")
+                .ok()
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(3600),
             cache_max_capacity: std::env::var("CACHE_MAX_CAPACITY")
                 .ok()
                 .and_then(|p| p.parse().ok())
                 .unwrap_or(10_000),
-            allowed_origin_suffix: std::env::var("ALLOWED_ORIGIN_SUFFIX")
-                .unwrap_or_else(|_| ".artistgrid.".to_string()),
-            allowed_origin_exact: std::env::var("ALLOWED_ORIGIN_EXACT")
-                .unwrap_or_else(|_| "artistgrid.cx".to_string()),
+            allowed_origin_exacts: parse_comma_list("ALLOWED_ORIGINS", "artistgrid.cx"),
+            allowed_origin_suffixes: parse_comma_list("ALLOWED_ORIGIN_SUFFIXES", ".artistgrid."),
+            cors_allowed_methods: std::env::var("CORS_ALLOWED_METHODS")
+                .unwrap_or_else(|_| "GET, OPTIONS".to_string()),
+            cors_allowed_headers: std::env::var("CORS_ALLOWED_HEADERS")
+                .unwrap_or_else(|_| "Content-Type".to_string()),
+            max_uri_path_len: std::env::var("MAX_URI_PATH_LEN")
+                .ok()
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(2048),
+            max_query_len: std::env::var("MAX_QUERY_LEN")
+                .ok()
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(2048),
+            request_timeout_seconds: std::env::var("REQUEST_TIMEOUT_SECONDS")
+                .ok()
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(15),
+            access_log_path: std::env::var("ACCESS_LOG_PATH").ok(),
+            access_log_max_bytes: std::env::var("ACCESS_LOG_MAX_BYTES")
+                .ok()
+                .and_then(|p| p.parse().ok())
+                .unwrap_or(50 * 1024 * 1024),
         })
     }
 }
@@ -64,6 +111,189 @@ struct AppState {
     client: Client,
     cache: Cache<String, CachedResponse>,
     config: Config,
+    metrics: Metrics,
+    access_log: Option<AccessLogger>,
+    // Coalesces background revalidations the same way `Cache::entry` coalesces
+    // cold-miss fetches: a key only enters here once, so concurrent STALE hits
+    // for it share a single in-flight refresh instead of each spawning their own.
+    inflight_revalidations: Arc<Mutex<HashSet<String>>>,
+}
+
+/// Hands access log lines off to a dedicated task that owns the log file, so
+/// request handlers never block on disk I/O or file rotation.
+#[derive(Clone)]
+struct AccessLogger {
+    sender: mpsc::UnboundedSender<String>,
+}
+
+impl AccessLogger {
+    fn spawn(path: String, max_bytes: u64) -> Self {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<String>();
+
+        tokio::spawn(async move {
+            let mut writer = match RotatingWriter::open(path.clone(), max_bytes) {
+                Ok(writer) => writer,
+                Err(e) => {
+                    error!("Failed to open access log {}: {}", path, e);
+                    return;
+                }
+            };
+            while let Some(line) = receiver.recv().await {
+                writer.write_line(&line);
+            }
+        });
+
+        Self { sender }
+    }
+
+    fn log(&self, line: String) {
+        // Best-effort: a closed channel just means we drop this line rather
+        // than block or crash the request that's logging it.
+        let _ = self.sender.send(line);
+    }
+}
+
+/// A buffered file writer that rotates to `<path>.1` (overwriting any previous
+/// backup) once the current file grows past `max_bytes`.
+struct RotatingWriter {
+    path: String,
+    max_bytes: u64,
+    written_bytes: u64,
+    file: BufWriter<std::fs::File>,
+}
+
+impl RotatingWriter {
+    fn open(path: String, max_bytes: u64) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written_bytes = file.metadata()?.len();
+        Ok(Self {
+            path,
+            max_bytes,
+            written_bytes,
+            file: BufWriter::new(file),
+        })
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.written_bytes >= self.max_bytes {
+            self.rotate();
+        }
+
+        if let Err(e) = writeln!(self.file, "{}", line) {
+            error!("Failed to write access log line: {}", e);
+            return;
+        }
+        if let Err(e) = self.file.flush() {
+            error!("Failed to flush access log: {}", e);
+            return;
+        }
+        self.written_bytes += line.len() as u64 + 1;
+    }
+
+    fn rotate(&mut self) {
+        let _ = self.file.flush();
+        let rotated_path = format!("{}.1", self.path);
+        if let Err(e) = std::fs::rename(&self.path, &rotated_path) {
+            error!("Failed to rotate access log {}: {}", self.path, e);
+            return;
+        }
+        match OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)
+        {
+            Ok(file) => {
+                self.file = BufWriter::new(file);
+                self.written_bytes = 0;
+            }
+            Err(e) => error!("Failed to reopen access log {} after rotation: {}", self.path, e),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Metrics {
+    registry: Registry,
+    cache_hits_total: IntCounter,
+    cache_misses_total: IntCounter,
+    upstream_requests_total: IntCounterVec,
+    upstream_errors_total: IntCounter,
+    cors_rejected_total: IntCounter,
+    upstream_latency_seconds: Histogram,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let cache_hits_total =
+            IntCounter::new("proxy_cache_hits_total", "Total number of cache hits").unwrap();
+        let cache_misses_total =
+            IntCounter::new("proxy_cache_misses_total", "Total number of cache misses").unwrap();
+        let upstream_requests_total = IntCounterVec::new(
+            Opts::new(
+                "proxy_upstream_requests_total",
+                "Total upstream requests, labeled by response status class",
+            ),
+            &["status_class"],
+        )
+        .unwrap();
+        let upstream_errors_total = IntCounter::new(
+            "proxy_upstream_errors_total",
+            "Total upstream requests that failed to send or whose body failed to read",
+        )
+        .unwrap();
+        let cors_rejected_total = IntCounter::new(
+            "proxy_cors_rejected_total",
+            "Total non-preflight requests rejected for coming from a disallowed Origin",
+        )
+        .unwrap();
+        let upstream_latency_seconds = Histogram::with_opts(HistogramOpts::new(
+            "proxy_upstream_latency_seconds",
+            "Upstream request round-trip latency in seconds",
+        ))
+        .unwrap();
+
+        registry
+            .register(Box::new(cache_hits_total.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(cache_misses_total.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(upstream_requests_total.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(upstream_errors_total.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(cors_rejected_total.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(upstream_latency_seconds.clone()))
+            .unwrap();
+
+        Self {
+            registry,
+            cache_hits_total,
+            cache_misses_total,
+            upstream_requests_total,
+            upstream_errors_total,
+            cors_rejected_total,
+            upstream_latency_seconds,
+        }
+    }
+}
+
+fn status_class(status: u16) -> &'static str {
+    match status / 100 {
+        2 => "2xx",
+        3 => "3xx",
+        4 => "4xx",
+        5 => "5xx",
+        _ => "other",
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -71,6 +301,15 @@ struct CachedResponse {
     status: u16,
     body: Bytes,
     content_type: Option<String>,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    fetched_at: Instant,
+}
+
+impl CachedResponse {
+    fn is_fresh(&self, fresh_ttl: Duration) -> bool {
+        self.fetched_at.elapsed() < fresh_ttl
+    }
 }
 
 fn is_allowed_origin(origin: &str, config: &Config) -> bool {
@@ -81,7 +320,14 @@ fn is_allowed_origin(origin: &str, config: &Config) -> bool {
 
     let host = host.split(':').next().unwrap_or(host);
 
-    host == config.allowed_origin_exact || host.ends_with(&config.allowed_origin_suffix)
+    config
+        .allowed_origin_exacts
+        .iter()
+        .any(|exact| host == exact)
+        || config
+            .allowed_origin_suffixes
+            .iter()
+            .any(|suffix| host.ends_with(suffix.as_str()))
 }
 
 async fn cors_middleware(
@@ -101,15 +347,23 @@ async fn cors_middleware(
                 return Response::builder()
                     .status(StatusCode::NO_CONTENT)
                     .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin_str.as_str())
-                    .header(header::ACCESS_CONTROL_ALLOW_METHODS, "GET, OPTIONS")
-                    .header(header::ACCESS_CONTROL_ALLOW_HEADERS, "Content-Type")
+                    .header(
+                        header::ACCESS_CONTROL_ALLOW_METHODS,
+                        state.config.cors_allowed_methods.as_str(),
+                    )
+                    .header(
+                        header::ACCESS_CONTROL_ALLOW_HEADERS,
+                        state.config.cors_allowed_headers.as_str(),
+                    )
                     .header(header::ACCESS_CONTROL_MAX_AGE, "86400")
+                    .header(header::VARY, "Origin")
                     .body(Body::empty())
                     .unwrap();
             }
         }
         return Response::builder()
             .status(StatusCode::FORBIDDEN)
+            .header(header::VARY, "Origin")
             .body(Body::from("Origin not allowed"))
             .unwrap();
     }
@@ -117,14 +371,30 @@ async fn cors_middleware(
     if let Some(ref origin_str) = origin {
         if !is_allowed_origin(origin_str, &state.config) {
             warn!("Blocked request from origin: {}", origin_str);
+            state.metrics.cors_rejected_total.inc();
+            if let Some(logger) = &state.access_log {
+                logger.log(format!(
+                    "method={} path={} cache_key=- status=CORS_REJECTED upstream_status=0 upstream_latency_ms=0.00 bytes={} origin={}",
+                    request.method(),
+                    quote_log_field(&request.uri().to_string()),
+                    "Origin not allowed".len(),
+                    quote_log_field(origin_str),
+                ));
+            }
             return Response::builder()
                 .status(StatusCode::FORBIDDEN)
+                .header(header::VARY, "Origin")
                 .body(Body::from("Origin not allowed"))
                 .unwrap();
         }
     }
 
     let mut response = next.run(request).await;
+    // The response shape depends on the request's Origin (whether CORS headers
+    // are attached at all), so Vary: Origin must be set regardless of outcome.
+    response
+        .headers_mut()
+        .insert(header::VARY, HeaderValue::from_static("Origin"));
 
     if let Some(origin_str) = origin {
         if is_allowed_origin(&origin_str, &state.config) {
@@ -135,49 +405,95 @@ async fn cors_middleware(
             );
             headers.insert(
                 header::ACCESS_CONTROL_ALLOW_METHODS,
-                HeaderValue::from_static("GET, OPTIONS"),
+                HeaderValue::from_str(&state.config.cors_allowed_methods)
+                    .unwrap_or_else(|_| HeaderValue::from_static("GET, OPTIONS")),
             );
-            headers.insert(header::VARY, HeaderValue::from_static("Origin"));
         }
     }
 
     response
 }
 
-async fn proxy_all(State(state): State<AppState>, uri: Uri) -> impl IntoResponse {
-    let path = uri.path();
-    let query = uri.query().map(|q| format!("?{}", q)).unwrap_or_default();
-    let cache_key = format!("{}{}", path, query);
-
-    if let Some(cached) = state.cache.get(&cache_key).await {
-        info!("Cache HIT for: {}", cache_key);
-        return build_response(&cached, true, &state.config);
-    }
-
-    info!("Cache MISS for: {}", cache_key);
-
+/// Fetches `path`+`query` from upstream, always resolving to a `CachedResponse`
+/// (transport/read failures are synthesized as a 502 body) so callers can drive
+/// this through `Cache::entry` without juggling a separate error type.
+/// `previous`, when given, is sent back to upstream as `If-None-Match`/
+/// `If-Modified-Since` so a `304` can be turned into a freshness bump instead
+/// of a full re-transfer.
+async fn fetch_upstream(
+    state: &AppState,
+    path: &str,
+    query: &str,
+    previous: Option<&CachedResponse>,
+) -> CachedResponse {
     let upstream_url = format!("{}{}{}", state.config.upstream_url, path, query);
 
-    let response = match state
+    let mut request = state
         .client
         .get(&upstream_url)
-        .header("X-Api-Key", &state.config.api_key)
-        .send()
-        .await
-    {
+        .header("X-Api-Key", &state.config.api_key);
+
+    if let Some(previous) = previous {
+        if let Some(ref etag) = previous.etag {
+            request = request.header(header::IF_NONE_MATCH, etag.as_str());
+        }
+        if let Some(ref last_modified) = previous.last_modified {
+            request = request.header(header::IF_MODIFIED_SINCE, last_modified.as_str());
+        }
+    }
+
+    let started_at = Instant::now();
+    let response = match request.send().await {
         Ok(resp) => resp,
         Err(e) => {
             error!("Upstream request failed for {}: {}", path, e);
-            return (
-                StatusCode::BAD_GATEWAY,
-                [(header::CONTENT_TYPE, "application/json")],
-                r#"{"error": "Upstream request failed"}"#.to_string(),
-            )
-                .into_response();
+            state.metrics.upstream_errors_total.inc();
+            state
+                .metrics
+                .upstream_latency_seconds
+                .observe(started_at.elapsed().as_secs_f64());
+            return CachedResponse {
+                status: StatusCode::BAD_GATEWAY.as_u16(),
+                body: Bytes::from_static(br#"{"error": "Upstream request failed"}"#),
+                content_type: Some("application/json".to_string()),
+                etag: None,
+                last_modified: None,
+                fetched_at: Instant::now(),
+            };
         }
     };
+    state
+        .metrics
+        .upstream_latency_seconds
+        .observe(started_at.elapsed().as_secs_f64());
 
     let status = response.status().as_u16();
+    state
+        .metrics
+        .upstream_requests_total
+        .with_label_values(&[status_class(status)])
+        .inc();
+
+    if response.status() == StatusCode::NOT_MODIFIED {
+        if let Some(previous) = previous {
+            info!("Upstream confirmed {} unchanged (304), extending freshness", path);
+            return CachedResponse {
+                fetched_at: Instant::now(),
+                ..previous.clone()
+            };
+        }
+    }
+
+    let etag = response
+        .headers()
+        .get(header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+    let last_modified = response
+        .headers()
+        .get(header::LAST_MODIFIED)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
     let content_type = response
         .headers()
         .get(header::CONTENT_TYPE)
@@ -188,42 +504,395 @@ async fn proxy_all(State(state): State<AppState>, uri: Uri) -> impl IntoResponse
         Ok(bytes) => bytes,
         Err(e) => {
             error!("Failed to read upstream response: {}", e);
+            state.metrics.upstream_errors_total.inc();
+            return CachedResponse {
+                status: StatusCode::BAD_GATEWAY.as_u16(),
+                body: Bytes::from_static(br#"{"error": "Failed to read response"}"#),
+                content_type: Some("application/json".to_string()),
+                etag: None,
+                last_modified: None,
+                fetched_at: Instant::now(),
+            };
+        }
+    };
+
+    CachedResponse {
+        status,
+        body,
+        content_type,
+        etag,
+        last_modified,
+        fetched_at: Instant::now(),
+    }
+}
+
+/// Re-fetches `cache_key` in the background and, on a 2xx response, refreshes
+/// the cached entry so the next request inside the fresh window gets new data.
+/// A failure here simply leaves the existing (still within its stale window)
+/// entry in place rather than propagating the error to anyone. Always releases
+/// `inflight_revalidations` on the way out so the key can be revalidated again
+/// once this refresh finishes.
+async fn revalidate_in_background(state: AppState, cache_key: String, path: String, query: String) {
+    let previous = state.cache.get(&cache_key).await;
+    let refreshed = fetch_upstream(&state, &path, &query, previous.as_ref()).await;
+    if (200..300).contains(&refreshed.status) {
+        state.cache.insert(cache_key.clone(), refreshed).await;
+    } else {
+        warn!("Background revalidation of {} failed with status {}", cache_key, refreshed.status);
+    }
+    state.inflight_revalidations.lock().await.remove(&cache_key);
+}
+
+/// What gets written to the access log for one request, gathered alongside
+/// whatever `Response` `proxy_all` ends up returning.
+struct AccessLogEntry {
+    cache_key: String,
+    cache_status: &'static str,
+    upstream_status: u16,
+    upstream_latency_ms: f64,
+    body_len: usize,
+}
+
+/// Quotes a field for the space-delimited `key=value` access log so
+/// request-controlled values (path, Origin) can't inject fake `key=value`
+/// pairs or break the line apart; escapes backslashes, quotes, and newlines
+/// logfmt-style.
+fn quote_log_field(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn log_access(
+    state: &AppState,
+    method: &Method,
+    path_and_query: &str,
+    origin: Option<&str>,
+    entry: &AccessLogEntry,
+) {
+    if let Some(logger) = &state.access_log {
+        logger.log(format!(
+            "method={} path={} cache_key={} status={} upstream_status={} upstream_latency_ms={:.2} bytes={} origin={}",
+            method,
+            quote_log_field(path_and_query),
+            quote_log_field(&entry.cache_key),
+            entry.cache_status,
+            entry.upstream_status,
+            entry.upstream_latency_ms,
+            entry.body_len,
+            quote_log_field(origin.unwrap_or("-")),
+        ));
+    }
+}
+
+async fn proxy_all(
+    State(state): State<AppState>,
+    method: Method,
+    headers: HeaderMap,
+    uri: Uri,
+) -> impl IntoResponse {
+    let path_and_query = uri
+        .path_and_query()
+        .map(|pq| pq.as_str().to_string())
+        .unwrap_or_else(|| uri.path().to_string());
+    let origin = headers
+        .get(header::ORIGIN)
+        .and_then(|v| v.to_str().ok())
+        .map(String::from);
+
+    let (response, entry) = proxy_all_inner(&state, &headers, &uri).await;
+    log_access(&state, &method, &path_and_query, origin.as_deref(), &entry);
+    response
+}
+
+async fn proxy_all_inner(
+    state: &AppState,
+    headers: &HeaderMap,
+    uri: &Uri,
+) -> (Response, AccessLogEntry) {
+    let path = uri.path().to_string();
+    let raw_query = uri.query().unwrap_or("");
+
+    if path.len() > state.config.max_uri_path_len || raw_query.len() > state.config.max_query_len {
+        warn!(
+            "Rejected oversized request: path_len={} query_len={}",
+            path.len(),
+            raw_query.len()
+        );
+        let body = r#"{"error": "URI too long"}"#;
+        let response = (
+            StatusCode::URI_TOO_LONG,
+            [(header::CONTENT_TYPE, "application/json")],
+            body.to_string(),
+        )
+            .into_response();
+        return (
+            response,
+            AccessLogEntry {
+                cache_key: format!("{}?{}", path, raw_query),
+                cache_status: "REJECTED",
+                upstream_status: 0,
+                upstream_latency_ms: 0.0,
+                body_len: body.len(),
+            },
+        );
+    }
+
+    let query = if raw_query.is_empty() {
+        String::new()
+    } else {
+        format!("?{}", raw_query)
+    };
+    let cache_key = format!("{}{}", path, query);
+    let fresh_ttl = Duration::from_secs(state.config.cache_ttl_seconds);
+    let request_etag = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok());
+
+    // The moka cache itself is kept alive for the full fresh+stale window (see
+    // `main`), so a hit here may be either fresh or merely stale-but-usable.
+    if let Some(cached) = state.cache.get(&cache_key).await {
+        if cached.is_fresh(fresh_ttl) {
+            info!("Cache HIT for: {}", cache_key);
+            state.metrics.cache_hits_total.inc();
+            let body_len = cached.body.len();
+            let response = build_response(&cached, "HIT", &state.config, request_etag);
             return (
+                response,
+                AccessLogEntry {
+                    cache_key,
+                    cache_status: "HIT",
+                    upstream_status: cached.status,
+                    upstream_latency_ms: 0.0,
+                    body_len,
+                },
+            );
+        }
+
+        info!("Cache STALE for: {}", cache_key);
+        state.metrics.cache_hits_total.inc();
+        // Only the request that actually claims `cache_key` here spawns a
+        // revalidation; concurrent STALE hits for the same key piggyback on
+        // it instead of each firing their own upstream fetch.
+        let should_revalidate = state
+            .inflight_revalidations
+            .lock()
+            .await
+            .insert(cache_key.clone());
+        if should_revalidate {
+            tokio::spawn(revalidate_in_background(
+                state.clone(),
+                cache_key.clone(),
+                path,
+                query,
+            ));
+        }
+        let body_len = cached.body.len();
+        let response = build_response(&cached, "STALE", &state.config, request_etag);
+        return (
+            response,
+            AccessLogEntry {
+                cache_key,
+                cache_status: "STALE",
+                upstream_status: cached.status,
+                upstream_latency_ms: 0.0,
+                body_len,
+            },
+        );
+    }
+
+    info!("Cache MISS for: {}", cache_key);
+    state.metrics.cache_misses_total.inc();
+
+    let fetch_state = state.clone();
+    let fetch_path = path.clone();
+    let fetch_query = query.clone();
+    let fetch_cache_key = cache_key.clone();
+
+    // `entry().or_insert_with` coalesces concurrent misses for the same key into a
+    // single upstream fetch; every waiter awaits the one in-flight future and gets
+    // its result back, so a thundering herd on a cold key only hits upstream once.
+    // We drive that coalesced future on its own task so a per-request timeout only
+    // ever drops *our* interest in the result: the shared fetch keeps running for
+    // every other waiter (and the cache) even if this particular caller gives up.
+    let fetch_started_at = Instant::now();
+    let fetch_task = tokio::spawn(async move {
+        fetch_state
+            .cache
+            .entry(fetch_cache_key)
+            .or_insert_with(async move { fetch_upstream(&fetch_state, &fetch_path, &fetch_query, None).await })
+            .await
+    });
+    let entry = match timeout(
+        Duration::from_secs(state.config.request_timeout_seconds),
+        fetch_task,
+    )
+    .await
+    {
+        Ok(Ok(entry)) => entry,
+        Ok(Err(join_err)) => {
+            error!("Upstream fetch task for {} failed: {}", cache_key, join_err);
+            let body = r#"{"error": "Upstream request failed"}"#;
+            let response = (
                 StatusCode::BAD_GATEWAY,
                 [(header::CONTENT_TYPE, "application/json")],
-                r#"{"error": "Failed to read response"}"#.to_string(),
+                body.to_string(),
+            )
+                .into_response();
+            return (
+                response,
+                AccessLogEntry {
+                    cache_key,
+                    cache_status: "MISS",
+                    upstream_status: 0,
+                    upstream_latency_ms: fetch_started_at.elapsed().as_secs_f64() * 1000.0,
+                    body_len: body.len(),
+                },
+            );
+        }
+        Err(_) => {
+            warn!(
+                "Upstream fetch for {} timed out after {}s",
+                cache_key, state.config.request_timeout_seconds
+            );
+            let body = r#"{"error": "Upstream request timed out"}"#;
+            let response = (
+                StatusCode::REQUEST_TIMEOUT,
+                [(header::CONTENT_TYPE, "application/json")],
+                body.to_string(),
             )
                 .into_response();
+            return (
+                response,
+                AccessLogEntry {
+                    cache_key,
+                    cache_status: "MISS",
+                    upstream_status: 0,
+                    upstream_latency_ms: fetch_started_at.elapsed().as_secs_f64() * 1000.0,
+                    body_len: body.len(),
+                },
+            );
         }
     };
 
-    let cached = CachedResponse {
-        status,
-        body,
-        content_type,
+    let is_fresh = entry.is_fresh();
+    let cached = entry.into_value();
+    let upstream_latency_ms = if is_fresh {
+        fetch_started_at.elapsed().as_secs_f64() * 1000.0
+    } else {
+        0.0
     };
 
-    if status >= 200 && status < 300 {
-        state.cache.insert(cache_key.clone(), cached.clone()).await;
+    if !(200..300).contains(&cached.status) && is_fresh {
+        // get_with/entry always caches the computed value, so scrub non-2xx
+        // results immediately to keep failures from being memoized.
+        state.cache.invalidate(&cache_key).await;
+
+        // A concurrent request may have refreshed this key between our initial
+        // `get` above and this failure; prefer serving that over a hard error.
+        if let Some(stale) = state.cache.get(&cache_key).await {
+            warn!(
+                "Upstream failed for {}, serving stale cached copy instead",
+                cache_key
+            );
+            let body_len = stale.body.len();
+            let mut response = build_response(&stale, "STALE", &state.config, request_etag);
+            response.headers_mut().insert(
+                "Warning",
+                HeaderValue::from_static("110 - \"Response is Stale\""),
+            );
+            return (
+                response,
+                AccessLogEntry {
+                    cache_key,
+                    cache_status: "STALE",
+                    upstream_status: stale.status,
+                    upstream_latency_ms,
+                    body_len,
+                },
+            );
+        }
     }
 
-    build_response(&cached, false, &state.config)
+    let cache_status = if is_fresh { "MISS" } else { "HIT" };
+    let body_len = cached.body.len();
+    let response = build_response(&cached, cache_status, &state.config, request_etag);
+    (
+        response,
+        AccessLogEntry {
+            cache_key,
+            cache_status,
+            upstream_status: cached.status,
+            upstream_latency_ms,
+            body_len,
+        },
+    )
 }
 
-fn build_response(cached: &CachedResponse, from_cache: bool, config: &Config) -> Response {
+/// The `Cache-Control` to hand to the actual client for a given cached entry.
+/// Non-2xx entries are never memoized at the moka layer (see `proxy_all_inner`),
+/// but `build_response` is also the one place a synthetic error response (e.g.
+/// a transport failure) gets rendered, so it must independently refuse to tell
+/// browsers/CDNs that a proxy-side failure is publicly cacheable. A STALE (or
+/// Warning-flagged stale-fallback) entry is, by definition, already past its
+/// fresh TTL, so it gets `max-age=0, must-revalidate` instead of the full
+/// fresh-window `max-age` — otherwise a downstream cache could hold an
+/// already-expired copy for another whole `cache_ttl_seconds` before
+/// re-checking, doubling the staleness this feature is meant to bound.
+fn cache_control_for(cached: &CachedResponse, cache_status: &str, config: &Config) -> String {
+    if !(200..300).contains(&cached.status) {
+        "no-store".to_string()
+    } else if cache_status == "STALE" {
+        "public, max-age=0, must-revalidate".to_string()
+    } else {
+        format!("public, max-age={}", config.cache_ttl_seconds)
+    }
+}
+
+fn build_response(
+    cached: &CachedResponse,
+    cache_status: &str,
+    config: &Config,
+    request_etag: Option<&str>,
+) -> Response {
+    let cache_control = cache_control_for(cached, cache_status, config);
+
+    if let (Some(request_tag), Some(cached_tag)) = (request_etag, cached.etag.as_deref()) {
+        if request_tag == cached_tag {
+            return Response::builder()
+                .status(StatusCode::NOT_MODIFIED)
+                .header(header::ETAG, cached_tag)
+                .header(header::CACHE_CONTROL, cache_control)
+                .header("X-Cache", cache_status)
+                .body(Body::empty())
+                .unwrap();
+        }
+    }
+
     let mut builder = Response::builder().status(cached.status);
 
     if let Some(ref ct) = cached.content_type {
         builder = builder.header(header::CONTENT_TYPE, ct.as_str());
     }
-
-    let cache_status = if from_cache { "HIT" } else { "MISS" };
+    if let Some(ref etag) = cached.etag {
+        builder = builder.header(header::ETAG, etag.as_str());
+    }
+    if let Some(ref last_modified) = cached.last_modified {
+        builder = builder.header(header::LAST_MODIFIED, last_modified.as_str());
+    }
 
     builder
-        .header(
-            header::CACHE_CONTROL,
-            format!("public, max-age={}", config.cache_ttl_seconds),
-        )
+        .header(header::CACHE_CONTROL, cache_control)
         .header("X-Cache", cache_status)
         .body(Body::from(cached.body.clone()))
         .unwrap()
@@ -233,11 +902,33 @@ async fn local_health() -> impl IntoResponse {
     (StatusCode::OK, "OK")
 }
 
+async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    let encoder = TextEncoder::new();
+    let metric_families = state.metrics.registry.gather();
+
+    let mut buffer = Vec::new();
+    if let Err(e) = encoder.encode(&metric_families, &mut buffer) {
+        error!("Failed to encode metrics: {}", e);
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            [(header::CONTENT_TYPE, "text/plain")],
+            "Failed to encode metrics".to_string(),
+        );
+    }
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, encoder.format_type())],
+        String::from_utf8(buffer).unwrap_or_default(),
+    )
+}
+
 async fn cache_stats(State(state): State<AppState>) -> impl IntoResponse {
     let stats = serde_json::json!({
         "entry_count": state.cache.entry_count(),
         "weighted_size": state.cache.weighted_size(),
         "ttl_seconds": state.config.cache_ttl_seconds,
+        "stale_seconds": state.config.cache_stale_seconds,
         "max_capacity": state.config.cache_max_capacity
     });
 
@@ -266,7 +957,7 @@ async fn main() {
 
     info!("Starting tracker-proxy v{}", env!("CARGO_PKG_VERSION"));
     info!("Upstream: {}", config.upstream_url);
-    info!("Cache TTL: {}s", config.cache_ttl_seconds);
+    info!("Cache TTL: {}s (stale for up to {}s more)", config.cache_ttl_seconds, config.cache_stale_seconds);
 
     let client = Client::builder()
         .timeout(Duration::from_secs(30))
@@ -280,20 +971,33 @@ async fn main() {
         .build()
         .expect("Failed to create HTTP client");
 
+    // Entries live for the full fresh+stale window; `proxy_all` is what decides
+    // whether a still-present entry counts as fresh or merely stale-but-usable.
     let cache: Cache<String, CachedResponse> = Cache::builder()
         .max_capacity(config.cache_max_capacity)
-        .time_to_live(Duration::from_secs(config.cache_ttl_seconds))
+        .time_to_live(Duration::from_secs(
+            config.cache_ttl_seconds + config.cache_stale_seconds,
+        ))
         .build();
 
+    let access_log = config.access_log_path.clone().map(|path| {
+        info!("Access log enabled at {}", path);
+        AccessLogger::spawn(path, config.access_log_max_bytes)
+    });
+
     let state = AppState {
         client,
         cache,
         config: config.clone(),
+        metrics: Metrics::new(),
+        access_log,
+        inflight_revalidations: Arc::new(Mutex::new(HashSet::new())),
     };
 
     let app = Router::new()
         .route("/_health", get(local_health))
         .route("/_stats", get(cache_stats))
+        .route("/_metrics", get(metrics_handler))
         .fallback(proxy_all)
         .layer(axum::middleware::from_fn_with_state(
             state.clone(),
@@ -320,3 +1024,183 @@ async fn shutdown_signal() {
     tokio::signal::ctrl_c().await.ok();
     info!("Shutting down...");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn test_config(upstream_url: String) -> Config {
+        Config {
+            api_key: "test-key".to_string(),
+            upstream_url,
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            cache_ttl_seconds: 600,
+            cache_stale_seconds: 3600,
+            cache_max_capacity: 10_000,
+            allowed_origin_exacts: vec!["artistgrid.cx".to_string()],
+            allowed_origin_suffixes: vec![".artistgrid.".to_string()],
+            cors_allowed_methods: "GET, OPTIONS".to_string(),
+            cors_allowed_headers: "Content-Type".to_string(),
+            max_uri_path_len: 2048,
+            max_query_len: 2048,
+            request_timeout_seconds: 15,
+            access_log_path: None,
+            access_log_max_bytes: 50 * 1024 * 1024,
+        }
+    }
+
+    fn test_state(config: Config) -> AppState {
+        let cache: Cache<String, CachedResponse> = Cache::builder()
+            .max_capacity(config.cache_max_capacity)
+            .time_to_live(Duration::from_secs(
+                config.cache_ttl_seconds + config.cache_stale_seconds,
+            ))
+            .build();
+
+        AppState {
+            client: Client::builder().build().unwrap(),
+            cache,
+            config,
+            metrics: Metrics::new(),
+            access_log: None,
+            inflight_revalidations: Arc::new(Mutex::new(HashSet::new())),
+        }
+    }
+
+    /// Regression test for the thundering-herd fix: many concurrent requests
+    /// for the same cold `cache_key` must coalesce into exactly one upstream
+    /// fetch via `Cache::entry().or_insert_with`, with every waiter getting
+    /// back the same result.
+    #[tokio::test]
+    async fn concurrent_cold_misses_coalesce_into_one_upstream_request() {
+        let hit_count = Arc::new(AtomicUsize::new(0));
+        let hit_count_for_server = hit_count.clone();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let mock_upstream = Router::new().route(
+            "/torrents",
+            get(move || {
+                let hit_count = hit_count_for_server.clone();
+                async move {
+                    hit_count.fetch_add(1, Ordering::SeqCst);
+                    // Sleep so concurrent waiters have time to pile up behind
+                    // this one in-flight fetch before it resolves.
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    (StatusCode::OK, "{}")
+                }
+            }),
+        );
+        tokio::spawn(async move {
+            axum::serve(listener, mock_upstream).await.unwrap();
+        });
+
+        let state = test_state(test_config(format!("http://{}", addr)));
+        let headers = HeaderMap::new();
+        let uri: Uri = "/torrents".parse().unwrap();
+
+        let waiters = (0..20).map(|_| {
+            let state = state.clone();
+            let headers = headers.clone();
+            let uri = uri.clone();
+            tokio::spawn(async move { proxy_all_inner(&state, &headers, &uri).await })
+        })
+        .collect::<Vec<_>>();
+
+        for waiter in waiters {
+            let (response, _entry) = waiter.await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        assert_eq!(
+            hit_count.load(Ordering::SeqCst),
+            1,
+            "expected concurrent misses for the same key to coalesce into a single upstream fetch"
+        );
+    }
+
+    /// When upstream answers a conditional refresh with `304 Not Modified`,
+    /// `fetch_upstream` should keep the existing body and only bump freshness
+    /// instead of re-transferring the payload.
+    #[tokio::test]
+    async fn matching_upstream_etag_skips_body_retransfer() {
+        let full_body_count = Arc::new(AtomicUsize::new(0));
+        let full_body_count_for_server = full_body_count.clone();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let mock_upstream = Router::new().route(
+            "/torrents",
+            get(move |headers: HeaderMap| {
+                let full_body_count = full_body_count_for_server.clone();
+                async move {
+                    if headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok())
+                        == Some("\"v1\"")
+                    {
+                        return Response::builder()
+                            .status(StatusCode::NOT_MODIFIED)
+                            .header(header::ETAG, "\"v1\"")
+                            .body(Body::empty())
+                            .unwrap();
+                    }
+                    full_body_count.fetch_add(1, Ordering::SeqCst);
+                    Response::builder()
+                        .status(StatusCode::OK)
+                        .header(header::ETAG, "\"v1\"")
+                        .header(header::CONTENT_TYPE, "application/json")
+                        .body(Body::from("{\"torrents\": []}"))
+                        .unwrap()
+                }
+            }),
+        );
+        tokio::spawn(async move {
+            axum::serve(listener, mock_upstream).await.unwrap();
+        });
+
+        let state = test_state(test_config(format!("http://{}", addr)));
+
+        let first = fetch_upstream(&state, "/torrents", "", None).await;
+        assert_eq!(first.status, 200);
+        assert_eq!(first.etag.as_deref(), Some("\"v1\""));
+
+        let second = fetch_upstream(&state, "/torrents", "", Some(&first)).await;
+        assert_eq!(second.status, 200);
+        assert_eq!(
+            second.body, first.body,
+            "a 304 from upstream should keep the previously cached body"
+        );
+        assert_eq!(
+            full_body_count.load(Ordering::SeqCst),
+            1,
+            "upstream should only have sent the full body once; the conditional refresh should have gotten a 304"
+        );
+    }
+
+    /// A client-supplied `If-None-Match` matching the cached entry's ETag
+    /// should short-circuit to an empty-bodied 304 rather than re-sending the
+    /// cached body.
+    #[tokio::test]
+    async fn client_if_none_match_hit_returns_304_with_empty_body() {
+        let config = test_config("http://127.0.0.1:1".to_string());
+        let cached = CachedResponse {
+            status: 200,
+            body: Bytes::from_static(b"{\"torrents\": []}"),
+            content_type: Some("application/json".to_string()),
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            fetched_at: Instant::now(),
+        };
+
+        let response = build_response(&cached, "HIT", &config, Some("\"v1\""));
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(body.is_empty(), "304 response must not include a body");
+    }
+}